@@ -1,6 +1,7 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
-use std::collections::VecDeque;
-use std::collections::HashSet; 
+use std::collections::HashSet;
 
 use rosrust;
 
@@ -14,105 +15,302 @@ use crate::graph::TfGraphNode;
 use crate::chain::TfIndividualTransformChain;
 use crate::utils::{
     get_inverse,
+    get_nanos,
     to_transform_stamped
 };
 use crate::msg;
 
 
-const _DEFAULT_CACHE_TIME: i32 = 10;
+const DEFAULT_CACHE_TIME: i32 = 10;
 const _MAX_GRAPH_DEPTH: u32 = 1000;
 
 
+/// A disjoint-set structure over frame ids, kept alongside `TfBuffer`'s adjacency map.
+///
+/// `child_transform_index` is undirected in practice (every transform is inserted together with
+/// its inverse), so this lets us answer "are these two frames already connected by some other
+/// path?" in close to O(1) instead of re-running a graph search for every insert and lookup.
+#[derive(Clone, Debug, Default)]
+struct UnionFind {
+    parent: HashMap<String, String>,
+}
+
+impl UnionFind {
+    /// Returns the representative of `frame`'s component, registering `frame` as its own root
+    /// the first time it is seen. Performs path compression along the way.
+    fn find(&mut self, frame: &str) -> String {
+        let parent = match self.parent.get(frame) {
+            Some(parent) => parent.clone(),
+            None => {
+                self.parent.insert(frame.to_string(), frame.to_string());
+                return frame.to_string();
+            }
+        };
+        if parent == frame {
+            return parent;
+        }
+        let root = self.find(&parent);
+        self.parent.insert(frame.to_string(), root.clone());
+        root
+    }
+
+    /// Merges the components containing `a` and `b`.
+    fn union(&mut self, a: &str, b: &str) {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a != root_b {
+            self.parent.insert(root_a, root_b);
+        }
+    }
+
+    /// Read-only root lookup for frames that may not have been registered yet; an unseen frame
+    /// is its own (singleton) root.
+    fn root(&self, frame: &str) -> String {
+        let mut current = frame.to_string();
+        while let Some(parent) = self.parent.get(&current) {
+            if *parent == current {
+                break;
+            }
+            current = parent.clone();
+        }
+        current
+    }
+
+    /// Whether `a` and `b` are already known to be in the same component.
+    fn connected(&self, a: &str, b: &str) -> bool {
+        self.root(a) == self.root(b)
+    }
+}
+
+/// An entry in the Dijkstra frontier, ordered so that `BinaryHeap` (a max-heap) pops the
+/// smallest accumulated weight first, and among equal weights the smallest frame id first.
+#[derive(Clone, Debug, PartialEq)]
+struct PathCandidate {
+    accumulated_weight: f64,
+    frame: String,
+}
+
+impl Eq for PathCandidate {}
+
+impl Ord for PathCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.accumulated_weight.partial_cmp(&self.accumulated_weight).unwrap_or(Ordering::Equal)
+            .then_with(|| other.frame.cmp(&self.frame))
+    }
+}
+
+impl PartialOrd for PathCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Whether `TfBuffer::to_dot` emits a `digraph` with `->` edges or a `graph` with `--` edges.
+/// Both draw one edge per connected pair of frames, starting at the lexicographically smaller
+/// frame id; a `Directed` arrow does not follow the transform's real parent->child direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DotKind {
+    Directed,
+    Undirected
+}
+
 #[derive(Clone, Debug)]
 pub struct TfBuffer {
     child_transform_index: HashMap<String, HashSet<String> >,
-    transform_data: HashMap<TfGraphNode, TfIndividualTransformChain>
+    transform_data: HashMap<TfGraphNode, TfIndividualTransformChain>,
+    components: UnionFind,
+    cache_time: rosrust::Duration
 }
 
 impl TfBuffer {
 
+    /// Builds a buffer whose chains retain `DEFAULT_CACHE_TIME` seconds of history.
     pub fn new() -> TfBuffer {
-        TfBuffer{child_transform_index: HashMap::new(), transform_data: HashMap::new()}
+        TfBuffer::with_cache_time(rosrust::Duration{sec: DEFAULT_CACHE_TIME, nsec: 0})
+    }
+
+    /// Builds a buffer whose chains evict samples older than `cache_time` behind their newest
+    /// entry.
+    pub fn with_cache_time(cache_time: rosrust::Duration) -> TfBuffer {
+        TfBuffer{child_transform_index: HashMap::new(), transform_data: HashMap::new(), components: UnionFind::default(), cache_time}
     }
 
-    pub fn handle_incoming_transforms(&mut self, transforms: msg::TFMessage, static_tf: bool) {
+    pub fn handle_incoming_transforms(&mut self, transforms: msg::TFMessage, static_tf: bool) -> Result<(), TfError> {
         for transform in transforms.transforms {
             let inverse_transform = get_inverse(transform.clone());
-            self.add_transform(transform, static_tf);
-            self.add_transform(inverse_transform, static_tf);
+            self.add_transform(transform, static_tf)?;
+            self.add_transform(inverse_transform, static_tf)?;
         }
+        Ok(())
     }
 
-    fn add_transform(&mut self, transform: msg::TransformStamped, static_tf: bool) {
-        //TODO: Detect is new transform will create a loop
-        if self.child_transform_index.contains_key(&transform.header.frame_id) {
-            let res = self.child_transform_index.get_mut(&transform.header.frame_id.clone()).unwrap();
-            res.insert(transform.child_frame_id.clone());
-        }
-        else {
-            self.child_transform_index.insert(transform.header.frame_id.clone(), HashSet::new());
-            let res = self.child_transform_index.get_mut(&transform.header.frame_id.clone()).unwrap();
-            res.insert(transform.child_frame_id.clone());
-        }
-        
-        let key = TfGraphNode{child: transform.child_frame_id.clone(), parent: transform.header.frame_id.clone()};
-        
-        if self.transform_data.contains_key(&key) {
-            let data = self.transform_data.get_mut(&key).unwrap();
+    /// Records `transform` as an edge between its parent and child frames. Unlike an early
+    /// version of this method, a new edge between two frames that are already connected by some
+    /// other chain (e.g. a second odometry source linking the same two frames) is accepted
+    /// rather than rejected as a cycle: `retrieve_transform_path`'s Dijkstra search is built to
+    /// handle graphs with redundant edges, and picking one such edge over another is exactly the
+    /// "multiple odometry sources" case this graph is meant to route around.
+    fn add_transform(&mut self, transform: msg::TransformStamped, static_tf: bool) -> Result<(), TfError> {
+        let parent = transform.header.frame_id.clone();
+        let child = transform.child_frame_id.clone();
+
+        self.child_transform_index.entry(parent.clone()).or_insert_with(HashSet::new).insert(child.clone());
+
+        let key = TfGraphNode{child: child.clone(), parent: parent.clone()};
+
+        if let Some(data) = self.transform_data.get_mut(&key) {
             data.add_to_buffer(transform.clone());
         }
         else {
-            let mut data = TfIndividualTransformChain::new(static_tf);
+            let mut data = TfIndividualTransformChain::new(static_tf, self.cache_time);
             data.add_to_buffer(transform.clone());
             self.transform_data.insert(key, data);
         }
+
+        self.components.union(&parent, &child);
+        Ok(())
+    }
+
+    /// The cost of traversing the edge between `parent` and `child`. `msg::TransformStamped`
+    /// carries no per-edge uncertainty measure in this crate, so every edge is a flat hop-count
+    /// of `1.0`; this is the single seam to change if a covariance (or similar) field is ever
+    /// added upstream, without having to touch the search itself.
+    fn edge_weight(&self, _parent: &str, _child: &str) -> f64 {
+        1.0
     }
- 
-    /// Retrieves the transform path
+
+    /// Retrieves the fewest-hops transform path from `from` to `to` via Dijkstra's algorithm,
+    /// weighting each edge by `edge_weight`. Now that `add_transform` stores redundant edges
+    /// (e.g. a second link between two frames that a prior chain already connects) instead of
+    /// rejecting them, a pair of frames can genuinely have more than one candidate path, and this
+    /// is what picks between them deterministically. With `edge_weight` hard-coded to a flat
+    /// hop-count, "fewest hops" is all this guarantees today — it is not minimal-uncertainty
+    /// routing, since nothing in this crate yet measures uncertainty to route by.
     fn retrieve_transform_path(&self, from: String, to: String) -> Result<Vec<String>, TfError> {
-        let mut res = vec!();
-        let mut frontier: VecDeque<String> = VecDeque::new();
-        let mut visited: HashSet<String> = HashSet::new();
+        if from != to && !self.components.connected(&from, &to) {
+            return Err(TfError::CouldNotFindTransform);
+        }
+
+        let mut accumulated: HashMap<String, f64> = HashMap::new();
         let mut parents: HashMap<String, String> = HashMap::new();
-        visited.insert(from.clone());
-        frontier.push_front(from.clone());
+        let mut frontier: BinaryHeap<PathCandidate> = BinaryHeap::new();
+
+        accumulated.insert(from.clone(), 0.0);
+        frontier.push(PathCandidate{accumulated_weight: 0.0, frame: from.clone()});
 
-        while !frontier.is_empty() {
-            let current_node = frontier.pop_front().unwrap();
-            if current_node == to {
+        while let Some(PathCandidate{accumulated_weight, frame}) = frontier.pop() {
+            if frame == to {
                 break;
             }
-            let children = self.child_transform_index.get(&current_node);
-            match children {
-                Some(children) => {
-                    for  v in children {
-                        if visited.contains(&v.to_string()) {
-                            continue;
-                        }
-                        parents.insert(v.to_string(), current_node.clone());
-                        frontier.push_front(v.to_string()); 
-                        visited.insert(v.to_string());  
-                    } 
-                },
-                None => {}
+            if accumulated_weight > *accumulated.get(&frame).unwrap_or(&f64::INFINITY) {
+                continue;
+            }
+            let children = match self.child_transform_index.get(&frame) {
+                Some(children) => children,
+                None => continue
+            };
+            for child in children {
+                let candidate_weight = accumulated_weight + self.edge_weight(&frame, child);
+                if candidate_weight < *accumulated.get(child).unwrap_or(&f64::INFINITY) {
+                    accumulated.insert(child.clone(), candidate_weight);
+                    parents.insert(child.clone(), frame.clone());
+                    frontier.push(PathCandidate{accumulated_weight: candidate_weight, frame: child.clone()});
+                }
             }
-            
         }
+
+        let mut res = vec!();
         let mut r = to;
         while r != from {
             res.push(r.clone());
             let parent = parents.get(&r);
-            
+
             match parent {
                 Some(x) => {
                     r = x.to_string()
                 },
-                None => return Err(TfError::CouldNotFindTransform) 
+                None => return Err(TfError::CouldNotFindTransform)
             }
         }
         res.reverse();
         Ok(res)
     }
+
+    /// Renders the frame tree as GraphViz DOT, labeling each edge with the latest translation,
+    /// its most recent stamp, the chain's oldest retained stamp, and whether it is static. This
+    /// reproduces tf2's `view_frames` diagnostic for spotting disconnected components or
+    /// unexpected static/dynamic classification without an external tool.
+    ///
+    /// `child_transform_index` always stores a transform together with its synthesized inverse,
+    /// so every connected pair of frames shows up as two directed entries with no record of
+    /// which one was actually broadcast. Both `DotKind::Directed` and `DotKind::Undirected`
+    /// therefore draw exactly one edge per pair, picking the lexicographically smaller frame id
+    /// as the edge's start; `Directed` is not guaranteed to point the way the real parent/child
+    /// relationship does.
+    pub fn to_dot(&self, kind: DotKind) -> String {
+        let (keyword, arrow) = match kind {
+            DotKind::Directed => ("digraph", "->"),
+            DotKind::Undirected => ("graph", "--")
+        };
+
+        let mut frames: HashSet<&String> = HashSet::new();
+        for (parent, children) in &self.child_transform_index {
+            frames.insert(parent);
+            frames.extend(children.iter());
+        }
+
+        let mut dot = format!("{} tf_tree {{\n", keyword);
+        for frame in &frames {
+            dot.push_str(&format!("    \"{}\";\n", frame));
+        }
+
+        // Exactly one edge per connected pair, regardless of `kind`: see the doc comment above
+        // for why `child_transform_index` can't tell us the "real" direction to prefer.
+        let mut rendered: HashSet<(String, String)> = HashSet::new();
+        for (parent, children) in &self.child_transform_index {
+            for child in children {
+                let (start, end) = if parent < child {
+                    (parent.clone(), child.clone())
+                } else {
+                    (child.clone(), parent.clone())
+                };
+                if !rendered.insert((start.clone(), end.clone())) {
+                    continue;
+                }
+
+                let key = TfGraphNode{parent: start.clone(), child: end.clone()};
+                let label = self.transform_data.get(&key).map_or_else(|| "unknown".to_string(), |chain| chain.to_dot_label());
+                dot.push_str(&format!("    \"{}\" {} \"{}\" [label=\"{}\"];\n", start, arrow, end, label));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Polling interval used while waiting for a transform to become available, mirroring the retry
+/// loop tf2's synchronous clients use while blocking on `can_transform(..., timeout)`.
+pub(crate) const CAN_TRANSFORM_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Repeatedly calls `check` until it reports success or `timeout` has elapsed, sleeping
+/// `CAN_TRANSFORM_POLL_INTERVAL` between attempts.
+///
+/// `check` is re-invoked from scratch on every attempt rather than handed a borrow that outlives
+/// the loop, specifically so a caller polling a `TfBuffer` behind a lock (see
+/// `TransformListener::can_transform`) can drop and re-acquire that lock between attempts instead
+/// of holding it — and starving the writer that would otherwise make the wait succeed — for the
+/// whole timeout.
+pub(crate) fn poll_until_available<F: FnMut() -> bool>(timeout: rosrust::Duration, mut check: F) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_nanos(get_nanos(timeout).max(0) as u64);
+    loop {
+        if check() {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(CAN_TRANSFORM_POLL_INTERVAL);
+    }
 }
 
 impl TransformInterface for TfBuffer {
@@ -165,9 +363,19 @@ impl TransformInterface for TfBuffer {
         Ok(msg)
     }
 
-    // TODO(MathuxNY-73) implement those methods
-    fn can_transform(&self, _target_frame: &str, _source_frame: &str, _time: rosrust::Time, _timeout: rosrust::Duration) -> Result<bool, TfError> {todo!()}
+    /// Blocks, retrying at a fixed interval, until `lookup_transform(source_frame, target_frame,
+    /// time)` succeeds or `timeout` elapses.
+    ///
+    /// This holds `&self` for the whole wait, which is only safe when the caller owns the
+    /// buffer outright. If the buffer sits behind an `Arc<RwLock<TfBuffer>>` (as with
+    /// `TransformListener`), calling this on a held read guard would starve the writer thread
+    /// that is supposed to make the wait succeed — use `TransformListener::can_transform`
+    /// instead, which re-acquires the lock between polls.
+    fn can_transform(&self, target_frame: &str, source_frame: &str, time: rosrust::Time, timeout: rosrust::Duration) -> Result<bool, TfError> {
+        Ok(poll_until_available(timeout, || self.lookup_transform(source_frame, target_frame, time).is_ok()))
+    }
 
+    // TODO(MathuxNY-73) implement those methods
     fn transform_to_output<'a, T>(&self, _input: &'a T, _output: &'a T, _target_frame: &str, _timeout: Option<rosrust::Duration>) -> &'a T {todo!()}
     fn transform_from_input<T>(&self, _input: T, _target: &str, _timeout: Option<rosrust::Duration>) -> T {todo!()}
 }
@@ -184,8 +392,19 @@ impl TransformWithTimeInterface for TfBuffer {
         Ok(to_transform_stamped(result, source_frame.to_string(), target_frame.to_string(), source_time))
     }
 
-    fn can_transform_with_time_travel(&self, _target_frame: &str, _target_time: rosrust::Time, _source_frame: &str, _source_time: rosrust::Time, _fixed_frame: &str, 
-        _timeout: rosrust::Duration) -> Result<bool, TfError> {todo!()}
+    /// Blocks, retrying at a fixed interval, until `lookup_transform_with_time_travel` succeeds
+    /// or `timeout` elapses.
+    ///
+    /// Same caveat as `TfBuffer::can_transform`: this holds `&self` for the whole wait, so a
+    /// buffer shared via `Arc<RwLock<TfBuffer>>` should be polled through
+    /// `TransformListener::can_transform_with_time_travel` instead, which drops the lock between
+    /// attempts.
+    fn can_transform_with_time_travel(&self, target_frame: &str, target_time: rosrust::Time, source_frame: &str, source_time: rosrust::Time, fixed_frame: &str,
+        timeout: rosrust::Duration) -> Result<bool, TfError> {
+        Ok(poll_until_available(timeout, || {
+            self.lookup_transform_with_time_travel(target_frame, target_time, source_frame, source_time, fixed_frame, rosrust::Duration{sec: 0, nsec: 0}).is_ok()
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -217,8 +436,8 @@ mod test {
            }
         };
         let world_to_item_inverse = get_inverse(world_to_item.clone());
-        buffer.add_transform(world_to_item, true);
-        buffer.add_transform(world_to_item_inverse, true);
+        buffer.add_transform(world_to_item, true).unwrap();
+        buffer.add_transform(world_to_item_inverse, true).unwrap();
 
         let world_to_base_link = msg::TransformStamped {
             child_frame_id: "base_link".to_string(),
@@ -237,8 +456,8 @@ mod test {
            }
         };
         let world_to_base_link_inv = get_inverse(world_to_base_link.clone());
-        buffer.add_transform(world_to_base_link, false);
-        buffer.add_transform(world_to_base_link_inv,  false);
+        buffer.add_transform(world_to_base_link, false).unwrap();
+        buffer.add_transform(world_to_base_link_inv,  false).unwrap();
 
         let base_link_to_camera = msg::TransformStamped {
             child_frame_id: "camera".to_string(),
@@ -257,8 +476,8 @@ mod test {
            }
         };
         let base_link_to_camera_inv = get_inverse(base_link_to_camera.clone());
-        buffer.add_transform(base_link_to_camera, true);
-        buffer.add_transform(base_link_to_camera_inv, true);
+        buffer.add_transform(base_link_to_camera, true).unwrap();
+        buffer.add_transform(base_link_to_camera_inv, true).unwrap();
     }
 
 
@@ -339,6 +558,77 @@ mod test {
         assert_approx_eq(res.unwrap(), expected);
     }
 
+    /// A second, redundant link between two frames already connected by another chain (the
+    /// "multiple odometry sources" scenario) is stored as an alternate edge rather than rejected,
+    /// and `retrieve_transform_path` picks the fewest-hops one deterministically.
+    #[test]
+    fn test_redundant_edge_is_stored_as_alternate_chain() {
+        let identity_rotation = msg::Quaternion{x: 0f64, y: 0f64, z: 0f64, w: 1f64};
+        let stamp = rosrust::Time{sec: 0, nsec: 0};
+
+        let world_to_a = msg::TransformStamped {
+            child_frame_id: "a".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 1f64, y: 0f64, z: 0f64}}
+        };
+        let a_to_b = msg::TransformStamped {
+            child_frame_id: "b".to_string(),
+            header: msg::Header{frame_id: "a".to_string(), stamp, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 0f64, y: 1f64, z: 0f64}}
+        };
+        // A direct, redundant link that connects two frames already reachable via world->a->b.
+        let world_to_b = msg::TransformStamped {
+            child_frame_id: "b".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation, translation: msg::Vector3{x: 5f64, y: 0f64, z: 0f64}}
+        };
+
+        let mut buffer = TfBuffer::new();
+        for transform in [world_to_a, a_to_b, world_to_b.clone()] {
+            let inverse = get_inverse(transform.clone());
+            buffer.add_transform(transform, true).unwrap();
+            buffer.add_transform(inverse, true).unwrap();
+        }
+
+        let res = buffer.lookup_transform("b", "world", stamp).unwrap();
+        let expected_direct = get_inverse(world_to_b).transform.translation;
+        assert_eq!(res.transform.translation, expected_direct, "expected the 1-hop direct link, not the 2-hop path through 'a'");
+    }
+
+    /// A lookup before the front of the chain's deque is `EvictedFromCache` once the chain has
+    /// aged samples out of `cache_time`, and `AttemptedLookupInPast` on a chain that has never
+    /// evicted anything.
+    #[test]
+    fn test_cache_eviction_distinguishes_past_from_evicted() {
+        let identity_rotation = msg::Quaternion{x: 0f64, y: 0f64, z: 0f64, w: 1f64};
+        let make_transform = |sec: u32, y: f64| msg::TransformStamped {
+            child_frame_id: "sensor".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp: rosrust::Time{sec, nsec: 0}, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 0f64, y, z: 0f64}}
+        };
+
+        // Four samples a second apart through a 1s cache age the earliest ones out.
+        let mut buffer = TfBuffer::with_cache_time(rosrust::Duration{sec: 1, nsec: 0});
+        for (sec, y) in [(10u32, 0f64), (11u32, 1f64), (12u32, 2f64), (13u32, 3f64)] {
+            let transform = make_transform(sec, y);
+            let inverse = get_inverse(transform.clone());
+            buffer.add_transform(transform, false).unwrap();
+            buffer.add_transform(inverse, false).unwrap();
+        }
+        let evicted = buffer.lookup_transform("world", "sensor", rosrust::Time{sec: 10, nsec: 0});
+        assert!(matches!(evicted, Err(TfError::EvictedFromCache)));
+
+        // The same query time against a chain that has never evicted anything (and never held
+        // data near it) predates the data instead of having been evicted from it.
+        let mut fresh_buffer = TfBuffer::with_cache_time(rosrust::Duration{sec: 1, nsec: 0});
+        let only_sample = make_transform(10, 0f64);
+        let only_sample_inv = get_inverse(only_sample.clone());
+        fresh_buffer.add_transform(only_sample, false).unwrap();
+        fresh_buffer.add_transform(only_sample_inv, false).unwrap();
+        let predates = fresh_buffer.lookup_transform("world", "sensor", rosrust::Time{sec: 5, nsec: 0});
+        assert!(matches!(predates, Err(TfError::AttemptedLookupInPast)));
+    }
+
     fn assert_approx_eq(msg1: msg::TransformStamped, msg2: msg::TransformStamped) {
         assert_eq!(msg1.header, msg2.header);
         assert_eq!(msg1.child_frame_id, msg2.child_frame_id);
@@ -352,4 +642,178 @@ mod test {
         assert!((msg1.transform.translation.y - msg2.transform.translation.y).abs() < 1e-9);
         assert!((msg1.transform.translation.z - msg2.transform.translation.z).abs() < 1e-9);
     }
+
+    /// A reusable odometer-style fixture modeled on geometry2's permuter: given a set of
+    /// parameter value lists, it walks the full Cartesian product of their combinations one
+    /// step at a time.
+    mod permuter {
+        pub struct Permuter<T: Clone> {
+            lists: Vec<Vec<T>>,
+            indices: Vec<usize>,
+            exhausted: bool
+        }
+
+        impl<T: Clone> Permuter<T> {
+            pub fn new(lists: Vec<Vec<T>>) -> Self {
+                let exhausted = lists.is_empty() || lists.iter().any(|list| list.is_empty());
+                let indices = vec![0; lists.len()];
+                Permuter{lists, indices, exhausted}
+            }
+
+            /// Rewinds the odometer back to the first combination.
+            pub fn reset(&mut self) {
+                self.indices.iter_mut().for_each(|i| *i = 0);
+                self.exhausted = self.lists.is_empty() || self.lists.iter().any(|list| list.is_empty());
+            }
+
+            fn current(&self) -> Vec<T> {
+                self.lists.iter().zip(self.indices.iter()).map(|(list, &i)| list[i].clone()).collect()
+            }
+
+            /// Advances the least-significant list by one, carrying into the next list whenever
+            /// one wraps. Returns `false` once every combination has been produced.
+            fn advance(&mut self) -> bool {
+                for i in 0..self.indices.len() {
+                    self.indices[i] += 1;
+                    if self.indices[i] < self.lists[i].len() {
+                        return true;
+                    }
+                    self.indices[i] = 0;
+                }
+                false
+            }
+        }
+
+        impl<T: Clone> Iterator for Permuter<T> {
+            type Item = Vec<T>;
+
+            fn next(&mut self) -> Option<Vec<T>> {
+                if self.exhausted {
+                    return None;
+                }
+                let combination = self.current();
+                self.exhausted = !self.advance();
+                Some(combination)
+            }
+        }
+    }
+
+    /// `reset()` rewinds the odometer without losing any combinations: replaying a `Permuter`
+    /// after resetting it should reproduce exactly the same sequence as the first pass.
+    #[test]
+    fn test_permuter_reset_replays_from_start() {
+        use permuter::Permuter;
+
+        let mut permuter = Permuter::new(vec!(vec!(0, 1, 2), vec!(10, 11)));
+        let first_pass: Vec<_> = std::iter::from_fn(|| permuter.next()).collect();
+        permuter.reset();
+        let second_pass: Vec<_> = std::iter::from_fn(|| permuter.next()).collect();
+        assert_eq!(first_pass, second_pass);
+    }
+
+    /// All orderings in which a set of `n` edges can be inserted into the buffer.
+    fn permutations_of(n: usize) -> Vec<Vec<usize>> {
+        fn helper(remaining: &mut Vec<usize>, current: &mut Vec<usize>, acc: &mut Vec<Vec<usize>>) {
+            if remaining.is_empty() {
+                acc.push(current.clone());
+                return;
+            }
+            for i in 0..remaining.len() {
+                let value = remaining.remove(i);
+                current.push(value);
+                helper(remaining, current, acc);
+                current.pop();
+                remaining.insert(i, value);
+            }
+        }
+        let mut acc = Vec::new();
+        helper(&mut (0..n).collect(), &mut Vec::new(), &mut acc);
+        acc
+    }
+
+    /// The same tree as `build_test_tree`, but as a list of (transform, static) edges that
+    /// callers insert in an arbitrary `order`, to probe whether insertion order affects the
+    /// result.
+    fn permuted_test_tree_edges(base_link_is_static: bool) -> Vec<(msg::TransformStamped, bool)> {
+        let identity_rotation = msg::Quaternion{x: 0f64, y: 0f64, z: 0f64, w: 1f64};
+        let world_to_item = msg::TransformStamped {
+            child_frame_id: "item".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp: rosrust::Time{sec: 0, nsec: 0}, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 1f64, y: 0f64, z: 0f64}}
+        };
+        let world_to_base_link_t0 = msg::TransformStamped {
+            child_frame_id: "base_link".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp: rosrust::Time{sec: 0, nsec: 0}, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 0f64, y: 0f64, z: 0f64}}
+        };
+        let world_to_base_link_t1 = msg::TransformStamped {
+            child_frame_id: "base_link".to_string(),
+            header: msg::Header{frame_id: "world".to_string(), stamp: rosrust::Time{sec: 1, nsec: 0}, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation.clone(), translation: msg::Vector3{x: 0f64, y: 1f64, z: 0f64}}
+        };
+        let base_link_to_camera = msg::TransformStamped {
+            child_frame_id: "camera".to_string(),
+            header: msg::Header{frame_id: "base_link".to_string(), stamp: rosrust::Time{sec: 0, nsec: 0}, seq: 1},
+            transform: msg::Transform{rotation: identity_rotation, translation: msg::Vector3{x: 0.5f64, y: 0f64, z: 0f64}}
+        };
+        vec!(
+            (world_to_item, true),
+            (world_to_base_link_t0, base_link_is_static),
+            (world_to_base_link_t1, base_link_is_static),
+            (base_link_to_camera, true)
+        )
+    }
+
+    fn build_permuted_test_tree(buffer: &mut TfBuffer, edges: &[(msg::TransformStamped, bool)], order: &[usize]) {
+        for &i in order {
+            let (transform, static_tf) = edges[i].clone();
+            let inverse_transform = get_inverse(transform.clone());
+            buffer.add_transform(transform, static_tf).unwrap();
+            buffer.add_transform(inverse_transform, static_tf).unwrap();
+        }
+    }
+
+    /// Feeds the same four edges to the buffer in every possible order, crossed with a couple of
+    /// query times and both a static and a dynamic classification of the `base_link` edge, and
+    /// checks that `lookup_transform` agrees with a fixed reference order regardless. This would
+    /// catch ordering-dependent bugs in `add_to_buffer`'s binary-search insertion and in
+    /// `retrieve_transform_path` that the hand-written tests above cannot.
+    #[test]
+    fn test_lookup_consistent_regardless_of_insertion_order() {
+        use permuter::Permuter;
+
+        let orders = permutations_of(4);
+        let order_indices: Vec<f64> = (0..orders.len()).map(|i| i as f64).collect();
+        let query_time_fractions = vec!(0.0f64, 0.7f64);
+        let base_link_static_flags = vec!(0.0f64, 1.0f64);
+
+        let mut permuter = Permuter::new(vec!(order_indices, query_time_fractions, base_link_static_flags));
+        let reference_order: Vec<usize> = (0..4).collect();
+
+        while let Some(combination) = permuter.next() {
+            let order = &orders[combination[0] as usize];
+            let query = rosrust::Time{sec: 0, nsec: (combination[1] * 1e9) as u32};
+            let base_link_is_static = combination[2] != 0.0;
+
+            let edges = permuted_test_tree_edges(base_link_is_static);
+
+            let mut buffer = TfBuffer::new();
+            build_permuted_test_tree(&mut buffer, &edges, order);
+
+            let mut reference_buffer = TfBuffer::new();
+            build_permuted_test_tree(&mut reference_buffer, &edges, &reference_order);
+
+            let result = buffer.lookup_transform("camera", "item", query).unwrap();
+            let reference = reference_buffer.lookup_transform("camera", "item", query).unwrap();
+            assert_eq!(result, reference, "order={:?} query={:?} base_link_static={}", order, query, base_link_is_static);
+
+            let travel_result = buffer.lookup_transform_with_time_travel(
+                "camera", query, "camera", rosrust::Time{sec: 0, nsec: 0}, "item", rosrust::Duration{sec: 0, nsec: 0}
+            ).unwrap();
+            let travel_reference = reference_buffer.lookup_transform_with_time_travel(
+                "camera", query, "camera", rosrust::Time{sec: 0, nsec: 0}, "item", rosrust::Duration{sec: 0, nsec: 0}
+            ).unwrap();
+            assert_eq!(travel_result, travel_reference, "time-travel order={:?} query={:?} base_link_static={}", order, query, base_link_is_static);
+        }
+    }
 }
\ No newline at end of file
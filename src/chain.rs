@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use crate::transforms;
 use crate::core::TfError;
 use crate::utils::{
@@ -7,37 +9,66 @@ use crate::utils::{
 use crate::msg;
 
 
-#[derive(Clone, Debug)] 
+#[derive(Clone, Debug)]
 pub struct TfIndividualTransformChain {
-    buffer_size: usize,
     static_tf: bool,
-    //TODO:  Implement a circular buffer. Current method is slowww.
-    transform_chain: Vec<msg::TransformStamped>
+    /// Samples older than `newest_stamp - cache_time` are evicted on every insert.
+    cache_time: rosrust::Duration,
+    transform_chain: VecDeque<msg::TransformStamped>,
+    /// Set once a sample has aged out of `cache_time`. From then on, every lookup before the
+    /// front of the deque reports `EvictedFromCache`; whether the requested time ever had data
+    /// is not tracked. A chain that has never evicted anything reports `AttemptedLookupInPast`.
+    evicted: bool
 }
 
 
 impl TfIndividualTransformChain {
-    pub fn new(static_tf: bool) -> Self {
-        return TfIndividualTransformChain{buffer_size: 100, transform_chain:Vec::new(), static_tf: static_tf};
+    pub fn new(static_tf: bool, cache_time: rosrust::Duration) -> Self {
+        TfIndividualTransformChain{static_tf, cache_time, transform_chain: VecDeque::new(), evicted: false}
+    }
+
+    /// A human-readable summary of this chain's latest sample, used to label edges in
+    /// `TfBuffer::to_dot`.
+    pub fn to_dot_label(&self) -> String {
+        let newest = match self.transform_chain.back() {
+            Some(newest) => newest,
+            None => return "empty".to_string()
+        };
+        let oldest_stamp = self.transform_chain.front().map_or(newest.header.stamp, |t| t.header.stamp);
+        let t = &newest.transform.translation;
+        format!(
+            "t=({:.3}, {:.3}, {:.3})\\nstamp={}.{:09}\\noldest={}.{:09}\\nstatic={}",
+            t.x, t.y, t.z,
+            newest.header.stamp.sec, newest.header.stamp.nsec,
+            oldest_stamp.sec, oldest_stamp.nsec,
+            self.static_tf
+        )
     }
 
     pub fn add_to_buffer(&mut self, msg: msg::TransformStamped) {
-        
-        let res = self.transform_chain.binary_search(&msg);
-        
-        match res {
-            Ok(x) => self.transform_chain.insert(x, msg),
-            Err(x) => self.transform_chain.insert(x, msg)
-        }
+        let index = self.transform_chain.make_contiguous().binary_search(&msg).unwrap_or_else(|x| x);
+        self.transform_chain.insert(index, msg);
 
-        if self.transform_chain.len() > self.buffer_size {
-            self.transform_chain.remove(0);
+        // Compared as an age rather than against `newest_stamp - cache_time`, which would wrap
+        // around when the newest stamp is smaller than `cache_time`.
+        let newest_stamp = self.transform_chain.back().unwrap().header.stamp;
+        while let Some(front) = self.transform_chain.front() {
+            if get_nanos(newest_stamp - front.header.stamp) > get_nanos(self.cache_time) {
+                self.transform_chain.pop_front();
+                self.evicted = true;
+            } else {
+                break;
+            }
         }
+
+        // Keep the deque contiguous so `get_closest_transform` can binary-search it through
+        // `as_slices` without requiring `&mut self`.
+        self.transform_chain.make_contiguous();
     }
 
     pub fn get_closest_transform(&self, time: rosrust::Time) -> Result<msg::TransformStamped, TfError> {
         if self.static_tf {
-            return Ok(self.transform_chain.get(self.transform_chain.len()-1).unwrap().clone());
+            return Ok(self.transform_chain.back().unwrap().clone());
         }
 
         let res = msg::TransformStamped {
@@ -57,22 +88,26 @@ impl TfIndividualTransformChain {
             }
         };
 
-        let res = self.transform_chain.binary_search(&res);
+        let (chain, _) = self.transform_chain.as_slices();
+        let res = chain.binary_search(&res);
         match res {
-            Ok(x)=> return Ok(self.transform_chain.get(x).unwrap().clone()),
+            Ok(x)=> return Ok(chain.get(x).unwrap().clone()),
             Err(x)=> {
                 if x == 0 {
+                    if self.evicted {
+                        return Err(TfError::EvictedFromCache);
+                    }
                     return Err(TfError::AttemptedLookupInPast);
                 }
-                if x >= self.transform_chain.len() {
+                if x >= chain.len() {
                     return Err(TfError::AttemptedLookUpInFuture)
                 }
-                let tf1 = self.transform_chain.get(x-1).unwrap().clone();
-                let tf2 = self.transform_chain.get(x).unwrap().clone();
-                let time1 = self.transform_chain.get(x-1).unwrap().header.stamp;
-                let time2 = self.transform_chain.get(x).unwrap().header.stamp;
-                let header = self.transform_chain.get(x).unwrap().header.clone();
-                let child_frame = self.transform_chain.get(x).unwrap().child_frame_id.clone();
+                let tf1 = chain.get(x-1).unwrap().clone();
+                let tf2 = chain.get(x).unwrap().clone();
+                let time1 = chain.get(x-1).unwrap().header.stamp;
+                let time2 = chain.get(x).unwrap().header.stamp;
+                let header = chain.get(x).unwrap().header.clone();
+                let child_frame = chain.get(x).unwrap().child_frame_id.clone();
                 let total_duration = get_nanos(time2 - time1) as f64;
                 let desired_duration = get_nanos(time - time1) as f64;
                 let weight = 1.0 - desired_duration/total_duration;
@@ -82,4 +117,4 @@ impl TfIndividualTransformChain {
             }
         }
     }
-}  
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,72 @@
+use std::sync::mpsc;
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use rosrust;
+
+use crate::buffer::{poll_until_available, TfBuffer};
+use crate::core::{TfError, TransformInterface, TransformWithTimeInterface};
+use crate::msg;
+
+/// A live equivalent of tf2_ros's `TransformListener`: subscribes to `/tf` and `/tf_static` and
+/// keeps feeding a shared `TfBuffer` as messages arrive, so callers can query it the same way
+/// they would a buffer populated from a bag file, just against streaming data.
+pub struct TransformListener {
+    buffer: Arc<RwLock<TfBuffer>>,
+    _tf_subscriber: rosrust::Subscriber,
+    _tf_static_subscriber: rosrust::Subscriber,
+}
+
+impl TransformListener {
+    /// Subscribes to `/tf` and `/tf_static` and starts feeding `buffer` from a background
+    /// thread. Subscriber callbacks only push onto an internal channel, so a consumer slowly
+    /// reading `buffer` never stalls the rosrust callback thread.
+    pub fn new(buffer: Arc<RwLock<TfBuffer>>) -> rosrust::error::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<(msg::TFMessage, bool)>();
+
+        let worker_buffer = buffer.clone();
+        thread::spawn(move || {
+            for (message, static_tf) in receiver {
+                if let Ok(mut buffer) = worker_buffer.write() {
+                    let _ = buffer.handle_incoming_transforms(message, static_tf);
+                }
+            }
+        });
+
+        let dynamic_sender = sender.clone();
+        let tf_subscriber = rosrust::subscribe("/tf", 100, move |message: msg::TFMessage| {
+            let _ = dynamic_sender.send((message, false));
+        })?;
+
+        let tf_static_subscriber = rosrust::subscribe("/tf_static", 100, move |message: msg::TFMessage| {
+            let _ = sender.send((message, true));
+        })?;
+
+        Ok(TransformListener{buffer, _tf_subscriber: tf_subscriber, _tf_static_subscriber: tf_static_subscriber})
+    }
+
+    /// Returns the buffer this listener is feeding, for querying from any thread.
+    pub fn buffer(&self) -> Arc<RwLock<TfBuffer>> {
+        self.buffer.clone()
+    }
+
+    /// Polls `lookup_transform(source_frame, target_frame, time)` against the shared buffer at a
+    /// fixed interval until it succeeds or `timeout` elapses, re-acquiring a short-lived read
+    /// lock on every attempt instead of holding one for the whole wait. That matters here
+    /// specifically: holding the read lock across the wait would block the background writer
+    /// thread above from ever applying the incoming transform this call is waiting for.
+    pub fn can_transform(&self, target_frame: &str, source_frame: &str, time: rosrust::Time, timeout: rosrust::Duration) -> Result<bool, TfError> {
+        Ok(poll_until_available(timeout, || {
+            self.buffer.read().map_or(false, |buffer| buffer.lookup_transform(source_frame, target_frame, time).is_ok())
+        }))
+    }
+
+    /// Same as `can_transform`, but polling `lookup_transform_with_time_travel`.
+    pub fn can_transform_with_time_travel(&self, target_frame: &str, target_time: rosrust::Time, source_frame: &str, source_time: rosrust::Time, fixed_frame: &str, timeout: rosrust::Duration) -> Result<bool, TfError> {
+        Ok(poll_until_available(timeout, || {
+            self.buffer.read().map_or(false, |buffer| {
+                buffer.lookup_transform_with_time_travel(target_frame, target_time, source_frame, source_time, fixed_frame, rosrust::Duration{sec: 0, nsec: 0}).is_ok()
+            })
+        }))
+    }
+}